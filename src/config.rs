@@ -1,4 +1,4 @@
-use std::{fs::File, io::Read, path::PathBuf};
+use std::{collections::HashMap, fs::File, io::Read, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +12,38 @@ pub struct Config {
     pub org_unit: Option<String>,
     pub org_name: Option<String>,
     pub thumbprint: Option<String>,
+    /// SHA-1 thumbprints of provisioned intermediate CAs, keyed by purpose
+    /// (e.g. "server"), alongside the root's own `thumbprint`.
+    #[serde(default)]
+    pub intermediate_thumbprints: Option<HashMap<String, String>>,
+    /// Base URL under which each issuer's CRL is published (e.g.
+    /// `https://example.com/crl` serves `.../rootCA.crl` and
+    /// `.../server-intermediateCA.crl`). Embedded as the CRL Distribution
+    /// Point extension on newly issued certs, pointing at whichever CRL
+    /// matches that cert's actual issuer. Left unset, new certs carry no
+    /// CRL DP extension.
+    #[serde(default)]
+    pub crl_url: Option<String>,
+    /// Validity period, in days, for the root and intermediate CAs. Leaf
+    /// lifetime is set per-invocation via `new --days`.
+    #[serde(default)]
+    pub ca_days: Option<i64>,
+    /// Key algorithm used for every `KeyPair` mkcert-rs generates, for both
+    /// CAs and leaf certs.
+    #[serde(default)]
+    pub key_algorithm: Option<KeyAlgorithm>,
+}
+
+/// Key algorithms offered for generated `KeyPair`s. `Rsa2048` isn't
+/// supported by rcgen's own key generation, so it shells out to `openssl`.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAlgorithm {
+    EcdsaP256,
+    #[default]
+    EcdsaP384,
+    Ed25519,
+    Rsa2048,
 }
 
 impl Default for Config {
@@ -23,6 +55,10 @@ impl Default for Config {
             org_unit: Some("Development".into()),
             org_name: Some("mkcert-rs".into()),
             thumbprint: None,
+            intermediate_thumbprints: None,
+            crl_url: None,
+            ca_days: Some(3650),
+            key_algorithm: Some(KeyAlgorithm::default()),
         }
     }
 }
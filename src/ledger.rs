@@ -0,0 +1,57 @@
+use std::{collections::HashMap, fs::File, io::Read, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::get_config_path, Error};
+
+/// Tracks one certificate issued by `new_cert`, so `revoke` can look it up by
+/// serial and CRL regeneration can see which certs have been revoked.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LedgerEntry {
+    pub serial: String,
+    pub issued_at: String,
+    pub revoked_at: Option<String>,
+    pub revocation_reason: Option<String>,
+    /// Which CA signed this cert ("root" or an intermediate purpose like
+    /// "server"), so CRL regeneration can put it on the matching CRL instead
+    /// of always the root's. Entries from before this field existed default
+    /// to "root", matching the flat, single-tier PKI they were issued under.
+    #[serde(default = "default_issuer")]
+    pub issuer: String,
+}
+
+fn default_issuer() -> String {
+    "root".to_string()
+}
+
+/// JSON ledger of every certificate issued by this CA, persisted next to
+/// `rootCA.crt`/`config.json`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Ledger {
+    pub entries: HashMap<String, LedgerEntry>,
+}
+
+impl Ledger {
+    fn path() -> Result<PathBuf, Error> {
+        Ok(get_config_path()?.join("ledger.json"))
+    }
+
+    pub fn read() -> Result<Ledger, Error> {
+        let path = Ledger::path()?;
+        if !path.exists() {
+            return Ok(Ledger::default());
+        }
+
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn write(&self) -> Result<(), Error> {
+        let path = Ledger::path()?;
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
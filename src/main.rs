@@ -1,10 +1,14 @@
 mod config;
+mod ledger;
 use clap::Parser;
 
 use rcgen::{
-    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair,
-    KeyUsagePurpose, PKCS_ECDSA_P384_SHA384,
+    BasicConstraints, CertificateParams, CertificateRevocationListParams,
+    CertificateSigningRequestParams, CustomExtension, DnType, ExtendedKeyUsagePurpose, IsCa,
+    Issuer, KeyIdMethod, KeyPair, KeyUsagePurpose, RevocationReason, RevokedCertParams,
+    SerialNumber, PKCS_ECDSA_P256_SHA256, PKCS_ECDSA_P384_SHA384, PKCS_ED25519,
 };
+use rand::RngCore;
 use sha1::{Digest, Sha1};
 use std::{
     fs::OpenOptions,
@@ -12,8 +16,12 @@ use std::{
     process::Command,
 };
 use thiserror::Error;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
 
-use crate::config::{get_config_path, Config};
+use crate::{
+    config::{get_config_path, Config, KeyAlgorithm},
+    ledger::{Ledger, LedgerEntry},
+};
 
 #[derive(Debug, Error)]
 enum Error {
@@ -23,12 +31,32 @@ enum Error {
     Rcgen(#[from] rcgen::Error),
     #[error("IO error: {0:#?}")]
     Io(#[from] std::io::Error),
-    #[error("Failed to add certificate to the system trust store")]
-    Cert(String),
+    /// A platform command (trust-store tool or `openssl`) exited unsuccessfully.
+    #[error("`{command}` failed (exit code {exit_code}): {stderr}")]
+    Command {
+        command: String,
+        exit_code: String,
+        stderr: String,
+    },
+    #[error("{0}")]
+    Message(String),
     #[error("Could not get home directory")]
     NoHomeDir,
 }
 
+/// Builds an `Error::Command` from a finished `std::process::Output`.
+fn command_error(command: &str, output: &std::process::Output) -> Error {
+    Error::Command {
+        command: command.to_string(),
+        exit_code: output
+            .status
+            .code()
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "terminated by signal".to_string()),
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    }
+}
+
 impl From<Error> for String {
     fn from(e: Error) -> Self {
         e.to_string()
@@ -53,19 +81,317 @@ enum Cli {
         /// Set Subject Alternate Names (example: localhost,google.com,postgres)
         #[arg(long, value_delimiter = ',')]
         sans: Vec<String>,
+        /// Validity period in days. Browsers reject leaf certs longer than 398 days.
+        #[arg(long, default_value_t = 90)]
+        days: i64,
+        /// Sign a PEM PKCS#10 CSR instead of generating a key locally (path to the .csr file).
+        /// The subject/SANs come from the CSR; `--sans` and `--key` are ignored.
+        #[arg(long)]
+        csr: Option<String>,
+    },
+    /// Revoke a previously issued certificate and regenerate the CRLs of the root and intermediate CAs
+    Revoke {
+        /// Serial number of the certificate to revoke, as hex (see the issued .crt or ledger.json)
+        #[arg(long)]
+        serial: String,
+        /// Revocation reason: unspecified, key-compromise, ca-compromise, affiliation-changed,
+        /// superseded, cessation-of-operation, certificate-hold, remove-from-crl,
+        /// privilege-withdrawn, aa-compromise
+        #[arg(long, default_value = "unspecified")]
+        reason: String,
     },
+    /// Diagnose whether the CA is actually installed in the system trust store
+    Status,
 }
 
 fn main() -> Result<(), String> {
     match Cli::parse() {
         Cli::InstallCa => install_ca(),
         Cli::UninstallCa => uninstall_ca(),
-        Cli::New { cert, key, sans } => new_cert(cert, key, sans),
+        Cli::New {
+            cert,
+            key,
+            sans,
+            days,
+            csr,
+        } => new_cert(cert, key, sans, days, csr),
+        Cli::Revoke { serial, reason } => revoke(serial, reason),
+        Cli::Status => status(),
     }?;
 
     Ok(())
 }
 
+#[cfg(target_os = "macos")]
+fn add_to_trust_store(root_cert_path: &std::path::Path) -> Result<(), Error> {
+    let home = dirs::home_dir().ok_or(Error::NoHomeDir)?;
+    let command = Command::new("security")
+        .arg("add-trusted-cert")
+        .arg("-k")
+        .arg(format!("{}/Library/Keychains/login.keychain-db", home.display()))
+        .arg(root_cert_path)
+        .output()?;
+
+    if command.status.success() {
+        println!("Added certificates to the system trust store");
+        Ok(())
+    } else {
+        let err = command_error("security add-trusted-cert", &command);
+        eprintln!("{err}");
+        Err(err)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn add_to_trust_store(root_cert_path: &std::path::Path) -> Result<(), Error> {
+    let command = Command::new("certutil")
+        .arg("-addstore")
+        .arg("Root")
+        .arg(root_cert_path)
+        .output()?;
+
+    if command.status.success() {
+        println!("Added certificates to the system trust store");
+        Ok(())
+    } else {
+        let err = command_error("certutil -addstore", &command);
+        eprintln!("{err}");
+        Err(err)
+    }
+}
+
+/// Returns the distro's CA anchor directory and the tool used to rebuild the
+/// trust bundle from it (Debian/Ubuntu vs. Fedora/RHEL layout).
+#[cfg(target_os = "linux")]
+fn linux_ca_anchor() -> (&'static str, &'static str) {
+    if std::path::Path::new("/etc/pki/ca-trust/source/anchors").is_dir() {
+        ("/etc/pki/ca-trust/source/anchors", "update-ca-trust")
+    } else {
+        ("/usr/local/share/ca-certificates", "update-ca-certificates")
+    }
+}
+
+#[cfg(target_os = "linux")]
+const LINUX_CA_FILE_NAME: &str = "mkcert-rs-rootCA.crt";
+
+#[cfg(target_os = "linux")]
+fn add_to_trust_store(root_cert_path: &std::path::Path) -> Result<(), Error> {
+    let (anchor_dir, update_cmd) = linux_ca_anchor();
+    let dest = std::path::Path::new(anchor_dir).join(LINUX_CA_FILE_NAME);
+    std::fs::copy(root_cert_path, &dest)?;
+
+    let command = Command::new(update_cmd).output()?;
+    if !command.status.success() {
+        let err = command_error(update_cmd, &command);
+        eprintln!("{err}");
+        return Err(err);
+    }
+
+    // Best-effort: also register with the NSS shared DB so Chrome/Firefox
+    // pick up the CA. Not every system has `certutil` (libnss3-tools), so
+    // failures here are not fatal.
+    if let Some(home) = dirs::home_dir() {
+        let nssdb = format!("sql:{}/.pki/nssdb", home.display());
+        let _ = Command::new("certutil")
+            .arg("-d")
+            .arg(&nssdb)
+            .arg("-A")
+            .arg("-t")
+            .arg("C,,")
+            .arg("-n")
+            .arg("mkcert-rs")
+            .arg("-i")
+            .arg(root_cert_path)
+            .output();
+    }
+
+    println!("Added certificates to the system trust store");
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn remove_from_trust_store(thumbprint: &str) -> Result<(), Error> {
+    let command = Command::new("security")
+        .arg("delete-certificate")
+        .arg("-Z")
+        .arg(thumbprint)
+        .output()?;
+
+    if command.status.success() {
+        println!("Removed certificates from the system trust store");
+        Ok(())
+    } else {
+        let err = command_error("security delete-certificate", &command);
+        eprintln!("{err}");
+        Err(err)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn remove_from_trust_store(thumbprint: &str) -> Result<(), Error> {
+    let command = Command::new("certutil")
+        .arg("-delstore")
+        .arg("Root")
+        .arg(thumbprint)
+        .output()?;
+
+    if command.status.success() {
+        println!("Removed certificates from the system trust store");
+        Ok(())
+    } else {
+        let err = command_error("certutil -delstore", &command);
+        eprintln!("{err}");
+        Err(err)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn remove_from_trust_store(_thumbprint: &str) -> Result<(), Error> {
+    let (anchor_dir, update_cmd) = linux_ca_anchor();
+    let dest = std::path::Path::new(anchor_dir).join(LINUX_CA_FILE_NAME);
+    if dest.exists() {
+        std::fs::remove_file(&dest)?;
+    }
+
+    let command = Command::new(update_cmd).output()?;
+    if !command.status.success() {
+        let err = command_error(update_cmd, &command);
+        eprintln!("{err}");
+        return Err(err);
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        let nssdb = format!("sql:{}/.pki/nssdb", home.display());
+        let _ = Command::new("certutil")
+            .arg("-d")
+            .arg(&nssdb)
+            .arg("-D")
+            .arg("-n")
+            .arg("mkcert-rs")
+            .output();
+    }
+
+    println!("Removed certificates from the system trust store");
+    Ok(())
+}
+
+/// Checks whether a cert with this thumbprint is actually present in the
+/// system trust store, as opposed to just recorded in `config.json`.
+#[cfg(target_os = "macos")]
+fn is_installed_in_trust_store(thumbprint: &str) -> Result<bool, Error> {
+    let home = dirs::home_dir().ok_or(Error::NoHomeDir)?;
+    let command = Command::new("security")
+        .arg("find-certificate")
+        .arg("-a")
+        .arg("-Z")
+        .arg(format!("{}/Library/Keychains/login.keychain-db", home.display()))
+        .output()?;
+
+    if !command.status.success() {
+        return Err(command_error("security find-certificate", &command));
+    }
+
+    let stdout = String::from_utf8_lossy(&command.stdout);
+    Ok(stdout.to_uppercase().contains(&thumbprint.to_uppercase()))
+}
+
+#[cfg(target_os = "windows")]
+fn is_installed_in_trust_store(thumbprint: &str) -> Result<bool, Error> {
+    let command = Command::new("certutil")
+        .arg("-verifystore")
+        .arg("Root")
+        .arg(thumbprint)
+        .output()?;
+
+    Ok(command.status.success())
+}
+
+#[cfg(target_os = "linux")]
+fn is_installed_in_trust_store(thumbprint: &str) -> Result<bool, Error> {
+    let (anchor_dir, _) = linux_ca_anchor();
+    let installed_path = std::path::Path::new(anchor_dir).join(LINUX_CA_FILE_NAME);
+    if !installed_path.exists() {
+        return Ok(false);
+    }
+
+    let pem_str = std::fs::read_to_string(&installed_path)?;
+    let parsed = pem::parse(&pem_str).map_err(|e| {
+        Error::Message(format!("Failed to parse {}: {e}", installed_path.display()))
+    })?;
+    Ok(thumbprint_of(parsed.contents()).eq_ignore_ascii_case(thumbprint))
+}
+
+/// Prints whether the CA is actually trusted: whether `rootCA.crt`/`.key`
+/// exist, whether `config.json`'s thumbprint matches what's on disk, and
+/// whether that thumbprint is present in the system trust store.
+fn status() -> Result<(), Error> {
+    let config = Config::read_config()?;
+    let path = get_config_path()?;
+
+    let root_cert_path = path.join("rootCA.crt");
+    let root_key_path = path.join("rootCA.key");
+
+    println!("Config directory: {}", path.display());
+    println!(
+        "rootCA.crt: {}",
+        if root_cert_path.exists() {
+            "present"
+        } else {
+            "missing"
+        }
+    );
+    println!(
+        "rootCA.key: {}",
+        if root_key_path.exists() {
+            "present"
+        } else {
+            "missing"
+        }
+    );
+
+    let disk_thumbprint = if root_cert_path.exists() {
+        let mut file = OpenOptions::new().read(true).open(&root_cert_path)?;
+        let mut pem_str = String::new();
+        file.read_to_string(&mut pem_str)?;
+        let parsed = pem::parse(&pem_str)
+            .map_err(|e| Error::Message(format!("Failed to parse rootCA.crt: {e}")))?;
+        Some(thumbprint_of(parsed.contents()))
+    } else {
+        None
+    };
+
+    match (&config.thumbprint, &disk_thumbprint) {
+        (Some(config_thumbprint), Some(disk_thumbprint))
+            if config_thumbprint.eq_ignore_ascii_case(disk_thumbprint) =>
+        {
+            if is_installed_in_trust_store(config_thumbprint)? {
+                println!(
+                    "Status: installed (thumbprint {config_thumbprint} is present in the system trust store)"
+                );
+            } else {
+                println!(
+                    "Status: not installed (thumbprint {config_thumbprint} matches rootCA.crt, \
+                     but was not found in the system trust store)"
+                );
+            }
+        }
+        (Some(config_thumbprint), Some(disk_thumbprint)) => {
+            println!(
+                "Status: stale (config.json thumbprint {config_thumbprint} does not match \
+                 rootCA.crt's actual thumbprint {disk_thumbprint}; re-run install-ca)"
+            );
+        }
+        (Some(_), None) => {
+            println!("Status: stale (config.json has a thumbprint but rootCA.crt is missing; re-run install-ca)");
+        }
+        (None, _) => {
+            println!("Status: not installed (no CA has been installed yet; run install-ca)");
+        }
+    }
+
+    Ok(())
+}
+
 fn install_ca() -> Result<(), Error> {
     let config = Config::read_config()?;
 
@@ -96,10 +422,19 @@ fn install_ca() -> Result<(), Error> {
         KeyUsagePurpose::DigitalSignature,
         KeyUsagePurpose::KeyEncipherment,
         KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
     ];
     cert_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
 
-    let private_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
+    let ca_days = config.ca_days.unwrap_or(3650);
+    validate_days(ca_days)?;
+
+    let now = OffsetDateTime::now_utc();
+    cert_params.not_before = now;
+    cert_params.not_after = now + Duration::days(ca_days);
+
+    let key_algorithm = config.key_algorithm.unwrap_or_default();
+    let private_key = generate_key_pair(key_algorithm)?;
 
     let ca_cert = cert_params.self_signed(&private_key)?;
 
@@ -113,41 +448,359 @@ fn install_ca() -> Result<(), Error> {
 
     println!("Created certificates in {}", path.display());
 
-    #[cfg(target_os = "macos")]
-    let command = {
-        let home = dirs::home_dir().unwrap();
-        let home = home.to_str().unwrap();
-        Command::new("security")
-            .arg("add-trusted-cert")
-            .arg("-k")
-            .arg(format!("{home}/Library/Keychains/login.keychain-db"))
-            .arg(&root_cert_path)
-            .output()?
-    };
+    add_to_trust_store(&root_cert_path)?;
+
+    let thumbprint = thumbprint_of(ca_cert.der());
+
+    let root_issuer = Issuer::from_ca_cert_pem(&ca_cert.pem(), private_key)?;
+    let mut intermediate_thumbprints = config.intermediate_thumbprints.clone().unwrap_or_default();
+    let intermediate_thumbprint = install_intermediate_ca(
+        &config,
+        &path,
+        INTERMEDIATE_SERVER_PURPOSE,
+        &root_issuer,
+    )?;
+    intermediate_thumbprints.insert(
+        INTERMEDIATE_SERVER_PURPOSE.to_string(),
+        intermediate_thumbprint,
+    );
 
-    #[cfg(target_os = "windows")]
-    let command = Command::new("certutil")
-        .arg("-addstore")
-        .arg("Root")
-        .arg(&root_cert_path)
-        .output()?;
+    Config::write_config(&Config {
+        thumbprint: Some(thumbprint),
+        intermediate_thumbprints: Some(intermediate_thumbprints),
+        ..config
+    })?;
 
-    if command.status.success() {
-        println!("Added certificates to the system trust store");
+    Ok(())
+}
+
+/// Name of the one intermediate CA mkcert-rs provisions today: a
+/// path-length-0-constrained signer used to issue server leaf certs,
+/// keeping the root key offline after `install-ca`.
+const INTERMEDIATE_SERVER_PURPOSE: &str = "server";
+
+/// Ledger/CRL key for the root CA itself, alongside the intermediate purpose
+/// keys (e.g. "server"), so revocation and CRL regeneration can be generic
+/// over "whichever CA actually signed this cert".
+const ROOT_ISSUER_KEY: &str = "root";
+
+/// File name of the CRL published by a given issuer key, matching the
+/// `{purpose}-intermediateCA.{crt,key}` naming used for intermediates.
+fn crl_file_name(issuer_key: &str) -> String {
+    if issuer_key == ROOT_ISSUER_KEY {
+        "rootCA.crl".to_string()
     } else {
-        let err_msg = format!("Error: {:#?}", command);
-        eprintln!("{err_msg}");
-        return Err(Error::Cert(err_msg));
+        format!("{issuer_key}-intermediateCA.crl")
     }
+}
 
+/// Generates a path-length-constrained intermediate CA signed by
+/// `root_issuer`, writes it to `{purpose}-intermediateCA.{crt,key}`, and
+/// returns its SHA-1 thumbprint.
+fn install_intermediate_ca(
+    config: &Config,
+    config_path: &std::path::Path,
+    purpose: &str,
+    root_issuer: &Issuer<'_, KeyPair>,
+) -> Result<String, Error> {
+    let mut cert_params = CertificateParams::default();
+
+    cert_params.distinguished_name.push(
+        DnType::CommonName,
+        format!(
+            "{} {purpose} Intermediate CA",
+            config.common_name.clone().unwrap_or_default()
+        ),
+    );
+    cert_params.distinguished_name.push(
+        DnType::LocalityName,
+        config.locality.clone().unwrap_or_default(),
+    );
+    cert_params.distinguished_name.push(
+        DnType::CountryName,
+        config.country.clone().unwrap_or_default(),
+    );
+    cert_params.distinguished_name.push(
+        DnType::OrganizationName,
+        config.org_name.clone().unwrap_or_default(),
+    );
+    cert_params.distinguished_name.push(
+        DnType::OrganizationalUnitName,
+        config.org_unit.clone().unwrap_or_default(),
+    );
+    cert_params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    cert_params.key_usages = vec![
+        KeyUsagePurpose::DigitalSignature,
+        KeyUsagePurpose::KeyEncipherment,
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+    ];
+    cert_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+
+    let ca_days = config.ca_days.unwrap_or(3650);
+    validate_days(ca_days)?;
+
+    let now = OffsetDateTime::now_utc();
+    cert_params.not_before = now;
+    cert_params.not_after = now + Duration::days(ca_days);
+
+    let intermediate_key = generate_key_pair(config.key_algorithm.unwrap_or_default())?;
+    let intermediate_cert = cert_params.signed_by(&intermediate_key, root_issuer)?;
+
+    let cert_path = config_path.join(format!("{purpose}-intermediateCA.crt"));
+    let key_path = config_path.join(format!("{purpose}-intermediateCA.key"));
+    std::fs::write(&cert_path, intermediate_cert.pem().as_bytes())?;
+    std::fs::write(&key_path, intermediate_key.serialize_pem().as_bytes())?;
+
+    Ok(thumbprint_of(intermediate_cert.der()))
+}
+
+fn thumbprint_of(der: &[u8]) -> String {
     let mut hasher = Sha1::new();
-    hasher.update(ca_cert.der());
-    let thumbprint_bytes = hasher.finalize();
-    let thumbprint = format!("{:X}", thumbprint_bytes);
-    Config::write_config(&Config {
-        thumbprint: Some(thumbprint),
-        ..config
+    hasher.update(der);
+    format!("{:X}", hasher.finalize())
+}
+
+/// Generates a `KeyPair` for the configured algorithm. rcgen can't generate
+/// RSA keys itself, so that case shells out to `openssl` and loads the result.
+fn generate_key_pair(algorithm: KeyAlgorithm) -> Result<KeyPair, Error> {
+    let signature_algorithm = match algorithm {
+        KeyAlgorithm::EcdsaP256 => &PKCS_ECDSA_P256_SHA256,
+        KeyAlgorithm::EcdsaP384 => &PKCS_ECDSA_P384_SHA384,
+        KeyAlgorithm::Ed25519 => &PKCS_ED25519,
+        KeyAlgorithm::Rsa2048 => {
+            let command = Command::new("openssl")
+                .arg("genpkey")
+                .arg("-algorithm")
+                .arg("RSA")
+                .arg("-pkeyopt")
+                .arg("rsa_keygen_bits:2048")
+                .output()?;
+
+            if !command.status.success() {
+                return Err(command_error("openssl genpkey", &command));
+            }
+
+            return Ok(KeyPair::from_pem(&String::from_utf8_lossy(
+                &command.stdout,
+            ))?);
+        }
+    };
+
+    Ok(KeyPair::generate_for(signature_algorithm)?)
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, Error> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(Error::Message(format!("Invalid hex serial: {hex}")));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| Error::Message(format!("Invalid hex serial: {hex}")))
+        })
+        .collect()
+}
+
+/// Generates a 20-byte random CA serial number, clearing the top bit so the
+/// DER INTEGER encoding is never mistaken for negative.
+fn random_serial_bytes() -> [u8; 20] {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes[0] &= 0x7f;
+    bytes
+}
+
+fn now_rfc3339() -> Result<String, Error> {
+    OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .map_err(|e| Error::Message(format!("Failed to format timestamp: {e}")))
+}
+
+/// Rejects non-positive validity periods before they reach `not_before`/
+/// `not_after` math, where a negative value would silently produce an
+/// already-expired cert instead of a clear error.
+fn validate_days(days: i64) -> Result<(), Error> {
+    if days <= 0 {
+        return Err(Error::Message(format!(
+            "Validity period must be a positive number of days, got {days}"
+        )));
+    }
+    Ok(())
+}
+
+/// DER tag+length+value encoding, used to hand-build the CRL Distribution
+/// Point extension since rcgen has no builder for it.
+fn der_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    if content.len() < 0x80 {
+        out.push(content.len() as u8);
+    } else {
+        let len_bytes = content.len().to_be_bytes();
+        let len_bytes = &len_bytes[len_bytes.iter().position(|&b| b != 0).unwrap_or(7)..];
+        out.push(0x80 | len_bytes.len() as u8);
+        out.extend_from_slice(len_bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Builds a CRLDistributionPoints extension (RFC 5280 §4.2.1.13, OID
+/// 2.5.29.31) pointing at the CRL for `issuer_key` under `crl_base_url`
+/// (see `crl_file_name`).
+fn crl_distribution_point_extension(crl_base_url: &str, issuer_key: &str) -> CustomExtension {
+    let url = format!(
+        "{}/{}",
+        crl_base_url.trim_end_matches('/'),
+        crl_file_name(issuer_key)
+    );
+    let general_name = der_tlv(0x86, url.as_bytes()); // [6] IA5String (uniformResourceIdentifier)
+    let full_name = der_tlv(0xA0, &general_name); // DistributionPointName::fullName [0]
+    let distribution_point_name = der_tlv(0xA0, &full_name); // DistributionPoint::distributionPoint [0]
+    let distribution_point = der_tlv(0x30, &distribution_point_name); // DistributionPoint ::= SEQUENCE
+    let crl_dist_points = der_tlv(0x30, &distribution_point); // CRLDistPointSyntax ::= SEQUENCE OF
+
+    CustomExtension::from_oid_content(&[2, 5, 29, 31], crl_dist_points)
+}
+
+fn parse_revocation_reason(reason: &str) -> Result<RevocationReason, Error> {
+    match reason {
+        "unspecified" => Ok(RevocationReason::Unspecified),
+        "key-compromise" => Ok(RevocationReason::KeyCompromise),
+        "ca-compromise" => Ok(RevocationReason::CaCompromise),
+        "affiliation-changed" => Ok(RevocationReason::AffiliationChanged),
+        "superseded" => Ok(RevocationReason::Superseded),
+        "cessation-of-operation" => Ok(RevocationReason::CessationOfOperation),
+        "certificate-hold" => Ok(RevocationReason::CertificateHold),
+        "remove-from-crl" => Ok(RevocationReason::RemoveFromCrl),
+        "privilege-withdrawn" => Ok(RevocationReason::PrivilegeWithdrawn),
+        "aa-compromise" => Ok(RevocationReason::AaCompromise),
+        other => Err(Error::Message(format!("Unknown revocation reason: {other}"))),
+    }
+}
+
+fn revoke(serial: String, reason: String) -> Result<(), Error> {
+    let serial = serial.to_uppercase();
+    // Validate the reason before touching the ledger so a typo doesn't leave
+    // an entry half-revoked.
+    parse_revocation_reason(&reason)?;
+
+    let mut ledger = Ledger::read()?;
+    let entry = ledger.entries.get_mut(&serial).ok_or_else(|| {
+        Error::Message(format!(
+            "No issued certificate found with serial {serial} in the ledger"
+        ))
+    })?;
+
+    entry.revoked_at = Some(now_rfc3339()?);
+    entry.revocation_reason = Some(reason);
+    ledger.write()?;
+
+    regenerate_crl(&ledger)?;
+
+    println!("Revoked certificate {serial} and regenerated its issuer's CRL");
+    Ok(())
+}
+
+/// Rebuilds every issuer's CRL (the root's, plus the "server" intermediate's
+/// if one was provisioned), each listing only the certs that issuer signed.
+fn regenerate_crl(ledger: &Ledger) -> Result<(), Error> {
+    let path = get_config_path()?;
+
+    regenerate_crl_for_issuer(
+        ledger,
+        ROOT_ISSUER_KEY,
+        &path.join("rootCA.crt"),
+        &path.join("rootCA.key"),
+        &path.join(crl_file_name(ROOT_ISSUER_KEY)),
+    )?;
+
+    let intermediate_cert_path =
+        path.join(format!("{INTERMEDIATE_SERVER_PURPOSE}-intermediateCA.crt"));
+    let intermediate_key_path =
+        path.join(format!("{INTERMEDIATE_SERVER_PURPOSE}-intermediateCA.key"));
+    if intermediate_cert_path.exists() && intermediate_key_path.exists() {
+        regenerate_crl_for_issuer(
+            ledger,
+            INTERMEDIATE_SERVER_PURPOSE,
+            &intermediate_cert_path,
+            &intermediate_key_path,
+            &path.join(crl_file_name(INTERMEDIATE_SERVER_PURPOSE)),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Builds and writes the CRL for a single issuer, listing only the ledger
+/// entries that issuer actually signed.
+fn regenerate_crl_for_issuer(
+    ledger: &Ledger,
+    issuer_key: &str,
+    issuer_cert_path: &std::path::Path,
+    issuer_key_path: &std::path::Path,
+    crl_path: &std::path::Path,
+) -> Result<(), Error> {
+    let mut key_file = OpenOptions::new().read(true).open(issuer_key_path)?;
+    let mut key_str = String::new();
+    key_file.read_to_string(&mut key_str)?;
+
+    let mut cert_file = OpenOptions::new().read(true).open(issuer_cert_path)?;
+    let mut cert_str = String::new();
+    cert_file.read_to_string(&mut cert_str)?;
+
+    let key = KeyPair::from_pem(&key_str)?;
+    let issuer = Issuer::from_ca_cert_pem(&cert_str, key)?;
+
+    let mut revoked_certs = Vec::new();
+    for entry in ledger.entries.values() {
+        if entry.issuer != issuer_key {
+            continue;
+        }
+        let Some(revoked_at) = &entry.revoked_at else {
+            continue;
+        };
+        let reason = entry.revocation_reason.as_deref().unwrap_or("unspecified");
+
+        revoked_certs.push(RevokedCertParams {
+            serial_number: SerialNumber::from_slice(&hex_to_bytes(&entry.serial)?),
+            revocation_time: OffsetDateTime::parse(revoked_at, &Rfc3339)
+                .map_err(|e| Error::Message(format!("Failed to parse revocation time: {e}")))?,
+            reason_code: Some(parse_revocation_reason(reason)?),
+            invalidity_date: None,
+        });
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let crl_params = CertificateRevocationListParams {
+        this_update: now,
+        next_update: now + Duration::days(7),
+        crl_number: SerialNumber::from_slice(&random_serial_bytes()),
+        issuing_distribution_point: None,
+        revoked_certs,
+        key_identifier_method: KeyIdMethod::Sha256,
+    };
+
+    // A CA provisioned before `CrlSign` was added to its key usages (by an
+    // `install-ca` from before this fix) will fail here with
+    // `IssuerNotCrlSigner`; surface that as an actionable error rather than
+    // the raw rcgen message.
+    let crl = crl_params.signed_by(&issuer).map_err(|e| {
+        if matches!(e, rcgen::Error::IssuerNotCrlSigner) {
+            Error::Message(format!(
+                "{issuer_key} CA was provisioned without the CRL-sign key usage; \
+                 run install-ca again to reprovision it, then retry revoke"
+            ))
+        } else {
+            Error::Rcgen(e)
+        }
     })?;
+    std::fs::write(crl_path, crl.pem()?.as_bytes())?;
 
     Ok(())
 }
@@ -156,33 +809,13 @@ fn uninstall_ca() -> Result<(), Error> {
     let config = Config::read_config()?;
 
     let thumbprint = config.thumbprint.as_ref().ok_or_else(|| {
-        Error::Cert(
+        Error::Message(
             "CA thumbprint not found in config. Cannot uninstall. Was the CA ever installed?"
                 .to_string(),
         )
     })?;
 
-    #[cfg(target_os = "macos")]
-    let command = Command::new("security")
-        .arg("delete-certificate")
-        .arg("-Z")
-        .arg(thumbprint)
-        .output()?;
-
-    #[cfg(target_os = "windows")]
-    let command = Command::new("certutil")
-        .arg("-delstore")
-        .arg("Root")
-        .arg(thumbprint)
-        .output()?;
-
-    if command.status.success() {
-        println!("Removed certificates from the system trust store");
-    } else {
-        let err_msg = format!("Error: {:#?}", command);
-        eprintln!("{err_msg}");
-        return Err(Error::Cert(err_msg));
-    }
+    remove_from_trust_store(thumbprint)?;
 
     let path = get_config_path()?;
     std::fs::remove_dir_all(path)?;
@@ -195,7 +828,15 @@ fn uninstall_ca() -> Result<(), Error> {
     Ok(())
 }
 
-fn new_cert(cert_name: String, key_name: String, sans: Vec<String>) -> Result<(), Error> {
+fn new_cert(
+    cert_name: String,
+    key_name: String,
+    sans: Vec<String>,
+    days: i64,
+    csr: Option<String>,
+) -> Result<(), Error> {
+    validate_days(days)?;
+
     let config = Config::read_config()?;
 
     let path = get_config_path()?;
@@ -203,61 +844,147 @@ fn new_cert(cert_name: String, key_name: String, sans: Vec<String>) -> Result<()
     let root_cert_path = path.join("rootCA.crt");
     let root_key_path = path.join("rootCA.key");
 
-    let mut root_key_file = OpenOptions::new().read(true).open(&root_key_path)?;
-    let mut root_key_str = String::new();
-    root_key_file.read_to_string(&mut root_key_str)?;
-
-    let root_key = KeyPair::from_pem(&root_key_str)?;
-
-    let mut root_cert_file = OpenOptions::new().read(true).open(&root_cert_path)?;
-    let mut root_cert_str = String::new();
-    root_cert_file.read_to_string(&mut root_cert_str)?;
-
-    let root_cert = Issuer::from_ca_cert_pem(&root_cert_str, root_key)?;
-
-    let new_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
-    let mut new_certificate = CertificateParams::new(sans)?;
+    let intermediate_cert_path =
+        path.join(format!("{INTERMEDIATE_SERVER_PURPOSE}-intermediateCA.crt"));
+    let intermediate_key_path =
+        path.join(format!("{INTERMEDIATE_SERVER_PURPOSE}-intermediateCA.key"));
+
+    // Prefer signing off the server intermediate when one was provisioned by
+    // `install-ca`, keeping the root key offline; fall back to the flat,
+    // single-tier root for trees created before intermediates existed.
+    let (issuer, issuer_chain_pem, issuer_key) =
+        if intermediate_cert_path.exists() && intermediate_key_path.exists() {
+            let mut key_file = OpenOptions::new().read(true).open(&intermediate_key_path)?;
+            let mut key_str = String::new();
+            key_file.read_to_string(&mut key_str)?;
+
+            let mut cert_file = OpenOptions::new().read(true).open(&intermediate_cert_path)?;
+            let mut cert_str = String::new();
+            cert_file.read_to_string(&mut cert_str)?;
+
+            let key = KeyPair::from_pem(&key_str)?;
+            let issuer = Issuer::from_ca_cert_pem(&cert_str, key)?;
+            (issuer, Some(cert_str), INTERMEDIATE_SERVER_PURPOSE)
+        } else {
+            let mut root_key_file = OpenOptions::new().read(true).open(&root_key_path)?;
+            let mut root_key_str = String::new();
+            root_key_file.read_to_string(&mut root_key_str)?;
+
+            let root_key = KeyPair::from_pem(&root_key_str)?;
+
+            let mut root_cert_file = OpenOptions::new().read(true).open(&root_cert_path)?;
+            let mut root_cert_str = String::new();
+            root_cert_file.read_to_string(&mut root_cert_str)?;
+
+            let issuer = Issuer::from_ca_cert_pem(&root_cert_str, root_key)?;
+            (issuer, None, ROOT_ISSUER_KEY)
+        };
+
+    let now = OffsetDateTime::now_utc();
+    let serial_bytes = random_serial_bytes();
+    let serial_hex = bytes_to_hex(&serial_bytes);
+
+    // `--csr` signs a caller-supplied public key and never touches a private
+    // key; otherwise we generate the key pair ourselves as before.
+    let (new_certificate, new_key) = if let Some(csr_path) = csr {
+        let mut csr_file = OpenOptions::new().read(true).open(&csr_path)?;
+        let mut csr_pem = String::new();
+        csr_file.read_to_string(&mut csr_pem)?;
+
+        let mut csr_params = CertificateSigningRequestParams::from_pem(&csr_pem)
+            .map_err(|e| Error::Message(format!("Failed to parse CSR {csr_path}: {e}")))?;
+        csr_params.params.not_before = now;
+        csr_params.params.not_after = now + Duration::days(days);
+        csr_params.params.serial_number = Some(SerialNumber::from_slice(&serial_bytes));
+        if let Some(crl_url) = &config.crl_url {
+            csr_params
+                .params
+                .custom_extensions
+                .push(crl_distribution_point_extension(crl_url, issuer_key));
+        }
+
+        (csr_params.signed_by(&issuer)?, None)
+    } else {
+        let new_key = generate_key_pair(config.key_algorithm.unwrap_or_default())?;
+        let mut new_certificate = CertificateParams::new(sans)?;
+
+        new_certificate.not_before = now;
+        new_certificate.not_after = now + Duration::days(days);
+
+        new_certificate.distinguished_name.push(
+            DnType::CommonName,
+            config.common_name.clone().unwrap_or_default(),
+        );
+        new_certificate.distinguished_name.push(
+            DnType::LocalityName,
+            config.locality.clone().unwrap_or_default(),
+        );
+        new_certificate.distinguished_name.push(
+            DnType::CountryName,
+            config.country.clone().unwrap_or_default(),
+        );
+        new_certificate.distinguished_name.push(
+            DnType::OrganizationName,
+            config.org_name.clone().unwrap_or_default(),
+        );
+        new_certificate.distinguished_name.push(
+            DnType::OrganizationalUnitName,
+            config.org_unit.clone().unwrap_or_default(),
+        );
+
+        new_certificate.serial_number = Some(SerialNumber::from_slice(&serial_bytes));
+        if let Some(crl_url) = &config.crl_url {
+            new_certificate
+                .custom_extensions
+                .push(crl_distribution_point_extension(crl_url, issuer_key));
+        }
+
+        let new_certificate = new_certificate.signed_by(&new_key, &issuer)?;
+        (new_certificate, Some(new_key))
+    };
 
-    new_certificate.distinguished_name.push(
-        DnType::CommonName,
-        config.common_name.clone().unwrap_or_default(),
-    );
-    new_certificate.distinguished_name.push(
-        DnType::LocalityName,
-        config.locality.clone().unwrap_or_default(),
+    let mut ledger = Ledger::read()?;
+    ledger.entries.insert(
+        serial_hex.clone(),
+        LedgerEntry {
+            serial: serial_hex,
+            issued_at: now_rfc3339()?,
+            revoked_at: None,
+            revocation_reason: None,
+            issuer: issuer_key.to_string(),
+        },
     );
-    new_certificate.distinguished_name.push(
-        DnType::CountryName,
-        config.country.clone().unwrap_or_default(),
-    );
-    new_certificate.distinguished_name.push(
-        DnType::OrganizationName,
-        config.org_name.clone().unwrap_or_default(),
-    );
-    new_certificate.distinguished_name.push(
-        DnType::OrganizationalUnitName,
-        config.org_unit.clone().unwrap_or_default(),
-    );
-
-    let new_certificate = new_certificate.signed_by(&new_key, &root_cert)?;
+    ledger.write()?;
 
     let path = std::env::current_dir()?;
 
     let cert_path = path.join(&cert_name);
-    let key_path = path.join(&key_name);
 
     let mut cert_file = OpenOptions::new()
         .create(true)
         .write(true)
+        .truncate(true)
         .open(&cert_path)?;
 
-    let mut key_file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .open(&key_path)?;
+    // When issued off an intermediate, ship the full chain (leaf + intermediate)
+    // so verifiers that don't separately hold the intermediate can still build
+    // a path to the (offline) root.
+    let mut chain_pem = new_certificate.pem();
+    if let Some(intermediate_pem) = issuer_chain_pem {
+        chain_pem.push_str(&intermediate_pem);
+    }
+
+    cert_file.write_all(chain_pem.as_bytes())?;
 
-    cert_file.write_all(new_certificate.pem().as_bytes())?;
-    key_file.write_all(new_key.serialize_pem().as_bytes())?;
+    if let Some(new_key) = new_key {
+        let key_path = path.join(&key_name);
+        let mut key_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&key_path)?;
+        key_file.write_all(new_key.serialize_pem().as_bytes())?;
+    }
 
     println!("Created new certificate in {}", cert_path.display());
 